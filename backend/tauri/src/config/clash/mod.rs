@@ -7,7 +7,8 @@ use log::warn;
 use serde::{Deserialize, Serialize};
 use serde_yaml::{Mapping, Value};
 use std::{
-    net::{IpAddr, Ipv4Addr, SocketAddr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
     str::FromStr,
 };
 use tracing_attributes::instrument;
@@ -54,13 +55,185 @@ impl IClashTemp {
 
     fn guard(mut config: Mapping) -> Mapping {
         let port = Self::guard_mixed_port(&config);
-        let ctrl = Self::guard_server_ctrl(&config);
-
         config.insert("mixed-port".into(), port.into());
-        config.insert("external-controller".into(), ctrl.into());
+
+        // clash binds `external-controller` and `external-controller-unix` as
+        // independent listeners rather than alternatives, so only normalize the
+        // TCP key when the user actually asked for one; otherwise a unix/pipe-only
+        // setup would grow a synthesized, unwanted TCP listener on every save.
+        let has_tcp_ctrl = config.get("external-controller").and_then(Value::as_str).is_some();
+        if let Some(endpoint) = Self::guard_unix_ctrl(&config) {
+            if let Some(raw) = endpoint.config_value() {
+                config.insert("external-controller-unix".into(), raw.into());
+            }
+            if has_tcp_ctrl {
+                let tcp_addr = Self::guard_tcp_ctrl(&config);
+                config.insert("external-controller".into(), tcp_addr.to_string().into());
+            }
+        } else {
+            let tcp_addr = Self::guard_tcp_ctrl(&config);
+            config.insert("external-controller".into(), tcp_addr.to_string().into());
+        }
+
+        Self::guard_tun_mtu(&mut config);
         config
     }
 
+    const TUN_MTU_OVERHEAD: u16 = 80;
+    const TUN_MTU_MIN: u16 = 576;
+    const TUN_MTU_MAX: u16 = 9000;
+
+    /// When TUN is enabled and `tun.mtu` isn't set, mirrors the egress interface's
+    /// MTU (minus protocol overhead) into the config so users don't hit
+    /// fragmentation on links with a smaller-than-default MTU (PPPoE, some VPN
+    /// uplinks).
+    fn guard_tun_mtu(config: &mut Mapping) {
+        let Some(Value::Mapping(tun)) = config.get("tun") else {
+            return;
+        };
+        let tun_enabled = tun.get("enable").and_then(Value::as_bool).unwrap_or(false);
+        if !tun_enabled || tun.get("mtu").and_then(Value::as_u64).is_some() {
+            return;
+        }
+
+        let interface_name = config
+            .get("interface-name")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        let Some(mtu) = Self::detect_optimal_tun_mtu(interface_name.as_deref()) else {
+            return;
+        };
+
+        if let Some(Value::Mapping(tun)) = config.get_mut("tun") {
+            log::info!(target: "app", "auto-detected TUN mtu: {mtu}");
+            tun.insert("mtu".into(), mtu.into());
+        }
+    }
+
+    fn detect_optimal_tun_mtu(interface_name: Option<&str>) -> Option<u16> {
+        let egress_mtu = Self::query_interface_mtu(interface_name)?;
+        let mtu = egress_mtu.saturating_sub(Self::TUN_MTU_OVERHEAD);
+        Some(mtu.clamp(Self::TUN_MTU_MIN, Self::TUN_MTU_MAX))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn query_interface_mtu(interface_name: Option<&str>) -> Option<u16> {
+        let name = match interface_name {
+            Some(name) => name.to_owned(),
+            None => Self::default_route_interface()?,
+        };
+        std::fs::read_to_string(format!("/sys/class/net/{name}/mtu"))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn default_route_interface() -> Option<String> {
+        let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+        routes.lines().skip(1).find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let iface = fields.next()?;
+            let destination = fields.next()?;
+            (destination == "00000000").then(|| iface.to_owned())
+        })
+    }
+
+    #[cfg(target_os = "macos")]
+    fn query_interface_mtu(interface_name: Option<&str>) -> Option<u16> {
+        let name = match interface_name {
+            Some(name) => name.to_owned(),
+            None => Self::default_route_interface()?,
+        };
+        let output = std::process::Command::new("ifconfig")
+            .arg(&name)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .split_whitespace()
+            .skip_while(|&token| token != "mtu")
+            .nth(1)?
+            .parse()
+            .ok()
+    }
+
+    #[cfg(target_os = "macos")]
+    fn default_route_interface() -> Option<String> {
+        let output = std::process::Command::new("route")
+            .args(["-n", "get", "default"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix("interface:")
+                .map(|iface| iface.trim().to_owned())
+        })
+    }
+
+    #[cfg(target_os = "windows")]
+    fn query_interface_mtu(interface_name: Option<&str>) -> Option<u16> {
+        let name = match interface_name {
+            Some(name) => name.to_owned(),
+            None => Self::default_route_interface()?,
+        };
+        let output = std::process::Command::new("netsh")
+            .args(["interface", "ipv4", "show", "subinterfaces"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // Rows look like "  1500         1500  connected         123456     Ethernet"
+        // with the interface name as the last column.
+        stdout.lines().find_map(|line| {
+            let trimmed = line.trim();
+            if !trimmed.ends_with(name.as_str()) {
+                return None;
+            }
+            trimmed.split_whitespace().next()?.parse().ok()
+        })
+    }
+
+    #[cfg(target_os = "windows")]
+    fn default_route_interface() -> Option<String> {
+        let output = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "(Get-NetRoute -DestinationPrefix '0.0.0.0/0' | Sort-Object RouteMetric | Select-Object -First 1 -ExpandProperty InterfaceAlias)",
+            ])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let iface = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+        (!iface.is_empty()).then_some(iface)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    fn query_interface_mtu(_interface_name: Option<&str>) -> Option<u16> {
+        // No known way to query the egress interface's MTU on this platform, so
+        // `tun.mtu` stays unset and users here see no auto-detection benefit. This
+        // is `warn`, not `debug`, on purpose: silent no-ops here would otherwise
+        // look identical to a successful (but empty) detection.
+        log::warn!(
+            target: "app",
+            "automatic TUN mtu detection is not implemented on this platform; leaving `tun.mtu` unset"
+        );
+        None
+    }
+
     pub fn patch_config(&mut self, patch: Mapping) {
         for (key, value) in patch.into_iter() {
             self.0.insert(key, value);
@@ -68,9 +241,11 @@ impl IClashTemp {
     }
 
     pub fn save_config(&self) -> Result<()> {
+        let mut config = self.0.clone();
+        Self::guard_tun_mtu(&mut config);
         help::save_yaml(
             &dirs::clash_path()?,
-            &self.0,
+            &config,
             Some("# Generated by Clash Nyanpasu"),
         )
     }
@@ -79,6 +254,104 @@ impl IClashTemp {
         Self::guard_mixed_port(&self.0)
     }
 
+    /// Surfaces risky-but-valid configurations as structured diagnostics instead of
+    /// silently rewriting them (as `guard` does for ports/controller addresses).
+    /// The frontend is expected to show these to the user rather than have the
+    /// core silently "fix" or reject the profile.
+    pub fn validate(&self) -> Vec<ClashConfigDiagnostic> {
+        let config = &self.0;
+        let mut diagnostics = Vec::new();
+
+        let allow_lan = config
+            .get("allow-lan")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let mixed_port = Self::guard_mixed_port(config);
+        let secret_is_empty = config
+            .get("secret")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .is_empty();
+
+        if let Some(val_str) = config.get("external-controller").and_then(Value::as_str) {
+            if let Ok(ctrl) = SocketAddr::from_str(val_str.trim()) {
+                if allow_lan && ctrl.ip().is_unspecified() && secret_is_empty {
+                    diagnostics.push(ClashConfigDiagnostic::error(
+                        "external-controller",
+                        "`allow-lan` is enabled and `external-controller` binds a wildcard \
+                         address with no `secret` set; the controller is reachable from the \
+                         LAN with no authentication.",
+                    ));
+                }
+
+                if ctrl.port() == mixed_port {
+                    diagnostics.push(ClashConfigDiagnostic::error(
+                        "mixed-port",
+                        format!(
+                            "`mixed-port` ({mixed_port}) collides with the `external-controller` \
+                             port; they cannot share the same port."
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let dns = config.get("dns").and_then(Value::as_mapping);
+
+        let tun_enabled = config
+            .get("tun")
+            .and_then(Value::as_mapping)
+            .and_then(|tun| tun.get("enable"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let enhanced_mode = dns.and_then(|dns| dns.get("enhanced-mode")).and_then(Value::as_str);
+        if tun_enabled && enhanced_mode != Some("fake-ip") {
+            diagnostics.push(ClashConfigDiagnostic::warning(
+                "dns.enhanced-mode",
+                "TUN is enabled but `dns.enhanced-mode` is not `fake-ip`; TUN routing expects \
+                 fake-ip DNS and may misbehave without it.",
+            ));
+        }
+
+        if let Some(dns) = dns {
+            for key in ["nameserver", "fallback"] {
+                let Some(Value::Sequence(entries)) = dns.get(key) else {
+                    continue;
+                };
+                for entry in entries {
+                    let Some(url) = entry.as_str() else { continue };
+                    if !Self::is_parsable_nameserver(url) {
+                        diagnostics.push(ClashConfigDiagnostic::warning(
+                            format!("dns.{key}"),
+                            format!("`{url}` is not a parsable nameserver/fallback address."),
+                        ));
+                    }
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Accepts the shapes clash's DNS config allows: a bare ip, `ip:port`, or a
+    /// scheme-prefixed url such as `tls://`, `https://` or `dhcp://`.
+    fn is_parsable_nameserver(value: &str) -> bool {
+        let value = value.trim();
+        if value.is_empty() {
+            return false;
+        }
+        if let Some((_scheme, rest)) = value.split_once("://") {
+            return !rest.is_empty();
+        }
+        if IpAddr::from_str(value).is_ok() || SocketAddr::from_str(value).is_ok() {
+            return true;
+        }
+        match value.rsplit_once(':') {
+            Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+            None => false,
+        }
+    }
+
     pub fn get_client_info(&self) -> ClashInfo {
         let config = &self.0;
 
@@ -94,30 +367,119 @@ impl IClashTemp {
         }
     }
 
+    /// Fires the hook script configured for `event`, if any, in the background.
+    /// The script is run fire-and-forget; its stdout/stderr are captured and
+    /// logged rather than surfaced to the caller. The output is waited for at most
+    /// `HOOK_TIMEOUT`: a script still running then is not killed, and only the
+    /// timeout is logged, not its output.
+    #[allow(dead_code)]
+    pub fn run_hook(&self, event: IClashHookEvent) {
+        let Some(hooks) = self
+            .0
+            .get("hooks")
+            .and_then(|value| serde_yaml::from_value::<IClashHooks>(value.clone()).ok())
+        else {
+            return;
+        };
+        let Some(script) = event.script(&hooks).map(str::to_owned) else {
+            return;
+        };
+
+        let info = self.get_client_info();
+        let mode = self
+            .0
+            .get("mode")
+            .and_then(Value::as_str)
+            .unwrap_or("rule")
+            .to_owned();
+
+        std::thread::spawn(move || {
+            let env = [
+                ("CLASH_MIXED_PORT", info.port.to_string()),
+                ("CLASH_EXTERNAL_CONTROLLER", info.server.to_string()),
+                ("CLASH_SECRET", info.secret.clone().unwrap_or_default()),
+                ("CLASH_MODE", mode),
+            ];
+            if let Err(err) = Self::execute_hook_script(&script, &env, Self::HOOK_TIMEOUT) {
+                log::warn!(target: "app", "hook script `{script}` failed: {err}");
+            }
+        });
+    }
+
+    const HOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+    fn execute_hook_script(
+        script: &str,
+        env: &[(&str, String)],
+        timeout: std::time::Duration,
+    ) -> Result<()> {
+        let mut command = if cfg!(target_os = "windows") {
+            let mut command = std::process::Command::new("cmd");
+            command.arg("/C").arg(script);
+            command
+        } else {
+            let mut command = std::process::Command::new("sh");
+            command.arg("-c").arg(script);
+            command
+        };
+        command
+            .envs(env.iter().cloned())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+
+        let child = command.spawn()?;
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(child.wait_with_output());
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(output)) => {
+                if !output.stdout.is_empty() {
+                    log::info!(target: "app", "hook `{script}` stdout: {}", String::from_utf8_lossy(&output.stdout));
+                }
+                if !output.stderr.is_empty() {
+                    log::warn!(target: "app", "hook `{script}` stderr: {}", String::from_utf8_lossy(&output.stderr));
+                }
+                Ok(())
+            }
+            Ok(Err(err)) => Err(err.into()),
+            Err(_) => Err(anyhow::anyhow!(
+                "hook `{script}` timed out after {:?}",
+                timeout
+            )),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn get_external_controller_port(&self) -> u16 {
-        let server = self.get_client_info().server;
-        let port = server.split(':').last().unwrap_or("9090");
-        port.parse().unwrap_or(9090)
+        match self.get_client_info().server {
+            ClashControllerEndpoint::Tcp(addr) => addr.port(),
+            _ => 9090,
+        }
     }
 
+    /// Probes for a free port via the configured strategy and rewrites
+    /// `external-controller` if it had to move. IPC transports
+    /// (`external-controller-unix`) have no port to probe, so this is a no-op for
+    /// them.
     #[instrument]
     pub fn prepare_external_controller_port(&mut self) -> Result<()> {
+        let ClashControllerEndpoint::Tcp(mut server) = self.get_client_info().server else {
+            return Ok(());
+        };
         let strategy = Config::verge()
             .latest()
             .get_external_controller_port_strategy();
-        let server = self.get_client_info().server;
-        let (server_ip, server_port) = server.split_once(':').unwrap_or(("127.0.0.1", "9090"));
-        let server_port = server_port.parse::<u16>().unwrap_or(9090);
-        let port = get_clash_external_port(&strategy, server_port)?;
-        if port != server_port {
-            let new_server = format!("{}:{}", server_ip, port);
+        let port = get_clash_external_port(&strategy, server.port())?;
+        if port != server.port() {
+            server.set_port(port);
             warn!(
                 "The external controller port has been changed to {}",
-                new_server
+                server
             );
             let mut map = Mapping::new();
-            map.insert("external-controller".into(), new_server.into());
+            map.insert("external-controller".into(), server.to_string().into());
             self.patch_config(map);
         }
         Ok(())
@@ -138,59 +500,225 @@ impl IClashTemp {
         port
     }
 
-    pub fn guard_server_ctrl(config: &Mapping) -> String {
+    /// Resolves the controller endpoint the core should bind: an IPC transport
+    /// (`external-controller-unix`) takes priority when present, otherwise falls
+    /// back to the TCP `external-controller` address (defaulting to
+    /// `127.0.0.1:9090` when missing or unparsable).
+    pub fn guard_server_ctrl(config: &Mapping) -> ClashControllerEndpoint {
+        Self::guard_unix_ctrl(config)
+            .unwrap_or_else(|| ClashControllerEndpoint::Tcp(Self::guard_tcp_ctrl(config)))
+    }
+
+    fn guard_tcp_ctrl(config: &Mapping) -> SocketAddr {
         config
             .get("external-controller")
-            .and_then(|value| match value.as_str() {
-                Some(val_str) => {
-                    let val_str = val_str.trim();
-
-                    let val = match val_str.starts_with(':') {
-                        true => format!("127.0.0.1{val_str}"),
-                        false => val_str.to_owned(),
-                    };
-
-                    SocketAddr::from_str(val.as_str())
-                        .ok()
-                        .map(|s| s.to_string())
-                }
-                None => None,
+            .and_then(Value::as_str)
+            .and_then(|val_str| {
+                let val_str = val_str.trim();
+                let val = match val_str.starts_with(':') {
+                    true => format!("127.0.0.1{val_str}"),
+                    false => val_str.to_owned(),
+                };
+                SocketAddr::from_str(val.as_str()).ok()
             })
-            .unwrap_or("127.0.0.1:9090".into())
+            .unwrap_or_else(|| SocketAddr::from(([127, 0, 0, 1], 9090)))
     }
 
-    pub fn guard_client_ctrl(config: &Mapping) -> String {
-        let value = Self::guard_server_ctrl(config);
-        match SocketAddr::from_str(value.as_str()) {
-            Ok(mut socket) => {
+    fn guard_unix_ctrl(config: &Mapping) -> Option<ClashControllerEndpoint> {
+        let raw = config
+            .get("external-controller-unix")
+            .and_then(Value::as_str)?
+            .trim();
+        if raw.is_empty() {
+            return None;
+        }
+
+        #[cfg(target_os = "windows")]
+        if raw.starts_with(r"\\.\pipe\") {
+            return Some(ClashControllerEndpoint::NamedPipe(raw.to_owned()));
+        }
+
+        Some(ClashControllerEndpoint::Unix(PathBuf::from(raw)))
+    }
+
+    /// Same as [`Self::guard_server_ctrl`], but rewrites an unspecified (`0.0.0.0`
+    /// / `[::]`) TCP address to loopback so clients on the same host can actually
+    /// reach it. IPC transports have no such concept and pass through unchanged.
+    pub fn guard_client_ctrl(config: &Mapping) -> ClashControllerEndpoint {
+        match Self::guard_server_ctrl(config) {
+            ClashControllerEndpoint::Tcp(mut socket) => {
                 if socket.ip().is_unspecified() {
                     socket.set_ip(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
                 }
-                socket.to_string()
+                ClashControllerEndpoint::Tcp(socket)
             }
-            Err(_) => "127.0.0.1:9090".into(),
+            other => other,
         }
     }
 
     #[allow(unused)]
     pub fn get_tun_device_ip(&self) -> String {
-        let config = &self.0;
+        self.get_fake_ip_network_addrs()
+            .map(|(_gateway, device)| device.to_string())
+            // 默认IP
+            .unwrap_or_else(|| "198.18.0.2".to_string())
+    }
 
-        let ip = config
+    /// Derives the `(gateway, device)` pair from `dns.fake-ip-range` by masking the
+    /// configured address with its prefix length, instead of string-patching a
+    /// hardcoded `198.18.0.1/16`. Returns `None` when the field is missing or
+    /// the CIDR can't be parsed, so callers can fall back to the default.
+    fn get_fake_ip_network_addrs(&self) -> Option<(IpAddr, IpAddr)> {
+        let fake_ip_range = self
+            .0
             .get("dns")
-            .and_then(|value| match value {
-                Value::Mapping(val_map) => Some(val_map.get("fake-ip-range").and_then(
-                    |fake_ip_range| match fake_ip_range {
-                        Value::String(ip_range_val) => Some(ip_range_val.replace("1/16", "2")),
-                        _ => None,
-                    },
-                )),
-                _ => None,
-            })
-            // 默认IP
-            .unwrap_or(Some("198.18.0.2".to_string()));
+            .and_then(Value::as_mapping)
+            .and_then(|dns| dns.get("fake-ip-range"))
+            .and_then(Value::as_str)?;
 
-        ip.unwrap()
+        let (network, prefix) = Self::parse_fake_ip_cidr(fake_ip_range)?;
+        let gateway = Self::fake_ip_network_addr(network, prefix, 1)?;
+        let device = Self::fake_ip_network_addr(network, prefix, 2)?;
+        Some((gateway, device))
+    }
+
+    fn parse_fake_ip_cidr(cidr: &str) -> Option<(IpAddr, u8)> {
+        let (addr, prefix) = cidr.trim().split_once('/')?;
+        let addr = IpAddr::from_str(addr).ok()?;
+        let prefix = prefix.trim().parse::<u8>().ok()?;
+        Some((addr, prefix))
+    }
+
+    /// Masks `addr` by `prefix` to find the network base, then adds `offset` to it.
+    fn fake_ip_network_addr(addr: IpAddr, prefix: u8, offset: u8) -> Option<IpAddr> {
+        match addr {
+            IpAddr::V4(v4) => {
+                if prefix > 32 {
+                    return None;
+                }
+                let mask = (u32::MAX)
+                    .checked_shl(32 - prefix as u32)
+                    .unwrap_or(0);
+                let network = u32::from(v4) & mask;
+                let addr = network.checked_add(offset as u32)?;
+                Some(IpAddr::V4(Ipv4Addr::from(addr)))
+            }
+            IpAddr::V6(v6) => {
+                if prefix > 128 {
+                    return None;
+                }
+                let mask = (u128::MAX)
+                    .checked_shl(128 - prefix as u32)
+                    .unwrap_or(0);
+                let network = u128::from(v6) & mask;
+                let addr = network.checked_add(offset as u128)?;
+                Some(IpAddr::V6(Ipv6Addr::from(addr)))
+            }
+        }
+    }
+}
+
+/// Severity of a [`ClashConfigDiagnostic`]: a warning flags a risky-but-functional
+/// setup, an error flags a configuration that is unlikely to work as intended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClashConfigSeverity {
+    Warning,
+    Error,
+}
+
+/// A non-fatal finding from [`IClashTemp::validate`]. Unlike `guard`, which
+/// silently rewrites bad values, this is surfaced to the frontend so the user can
+/// decide what to do about it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ClashConfigDiagnostic {
+    pub severity: ClashConfigSeverity,
+    /// dotted config key path the diagnostic refers to, e.g. `dns.enhanced-mode`
+    pub key: String,
+    pub message: String,
+}
+
+impl ClashConfigDiagnostic {
+    fn warning(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: ClashConfigSeverity::Warning,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+
+    fn error(key: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: ClashConfigSeverity::Error,
+            key: key.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Points at which [`IClashTemp::run_hook`] fires a script from `IClashHooks`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum IClashHookEvent {
+    PreStart,
+    PostStart,
+    PreStop,
+    PostStop,
+    OnConfigChange,
+}
+
+impl IClashHookEvent {
+    fn script(self, hooks: &IClashHooks) -> Option<&str> {
+        match self {
+            Self::PreStart => hooks.pre_start.as_deref(),
+            Self::PostStart => hooks.post_start.as_deref(),
+            Self::PreStop => hooks.pre_stop.as_deref(),
+            Self::PostStop => hooks.post_stop.as_deref(),
+            Self::OnConfigChange => hooks.on_config_change.as_deref(),
+        }
+    }
+}
+
+/// Where the core's RESTful controller is reachable: a TCP address, or an IPC
+/// transport (a unix socket, or on Windows a named pipe) configured via
+/// `external-controller-unix`. IPC transports need no shared secret to be safe
+/// to use, since they aren't reachable over the network.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClashControllerEndpoint {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+    #[cfg(target_os = "windows")]
+    NamedPipe(String),
+}
+
+impl ClashControllerEndpoint {
+    /// The raw value clash's own config expects for this transport, i.e. the bare
+    /// path/pipe name without the `unix://`/`npipe://` prefix used by `Display`.
+    fn config_value(&self) -> Option<String> {
+        match self {
+            Self::Tcp(_) => None,
+            Self::Unix(path) => Some(path.display().to_string()),
+            #[cfg(target_os = "windows")]
+            Self::NamedPipe(name) => Some(name.clone()),
+        }
+    }
+}
+
+impl Default for ClashControllerEndpoint {
+    fn default() -> Self {
+        Self::Tcp(SocketAddr::from(([127, 0, 0, 1], 9090)))
+    }
+}
+
+impl std::fmt::Display for ClashControllerEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{addr}"),
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+            #[cfg(target_os = "windows")]
+            Self::NamedPipe(name) => write!(f, "npipe://{name}"),
+        }
     }
 }
 
@@ -198,8 +726,8 @@ impl IClashTemp {
 pub struct ClashInfo {
     /// clash core port
     pub port: u16,
-    /// same as `external-controller`
-    pub server: String,
+    /// the controller endpoint, same as `external-controller` / `external-controller-unix`
+    pub server: ClashControllerEndpoint,
     /// clash secret
     pub secret: Option<String>,
 }
@@ -214,10 +742,10 @@ fn test_clash_info() {
         IClashTemp(IClashTemp::guard(map)).get_client_info()
     }
 
-    fn get_result<S: Into<String>>(port: u16, server: S) -> ClashInfo {
+    fn get_result<S: AsRef<str>>(port: u16, server: S) -> ClashInfo {
         ClashInfo {
             port,
-            server: server.into(),
+            server: ClashControllerEndpoint::Tcp(SocketAddr::from_str(server.as_ref()).unwrap()),
             secret: None,
         }
     }
@@ -267,6 +795,185 @@ fn test_clash_info() {
     );
 }
 
+#[test]
+fn test_unix_controller_endpoint() {
+    let mut map = Mapping::new();
+    map.insert("external-controller-unix".into(), "/tmp/nyanpasu.sock".into());
+
+    let info = IClashTemp(IClashTemp::guard(map)).get_client_info();
+    assert_eq!(
+        info.server,
+        ClashControllerEndpoint::Unix(PathBuf::from("/tmp/nyanpasu.sock"))
+    );
+
+    // an empty external-controller-unix falls back to the TCP default
+    let mut map = Mapping::new();
+    map.insert("external-controller-unix".into(), "   ".into());
+    let info = IClashTemp(IClashTemp::guard(map)).get_client_info();
+    assert_eq!(
+        info.server,
+        ClashControllerEndpoint::Tcp(SocketAddr::from_str("127.0.0.1:9090").unwrap())
+    );
+}
+
+#[test]
+fn test_guard_does_not_synthesize_tcp_ctrl_when_unix_only() {
+    // a unix-only setup must not grow a synthesized TCP `external-controller`:
+    // clash binds both keys as independent listeners, not alternatives, so a
+    // leftover default would silently open an unwanted TCP controller.
+    let mut map = Mapping::new();
+    map.insert("external-controller-unix".into(), "/tmp/nyanpasu.sock".into());
+    let guarded = IClashTemp::guard(map);
+    assert!(guarded.get("external-controller").is_none());
+    assert_eq!(
+        guarded.get("external-controller-unix").and_then(Value::as_str),
+        Some("/tmp/nyanpasu.sock")
+    );
+
+    // if the user explicitly configured both transports, both are normalized
+    let mut map = Mapping::new();
+    map.insert("external-controller-unix".into(), "/tmp/nyanpasu.sock".into());
+    map.insert("external-controller".into(), "0.0.0.0:1234".into());
+    let guarded = IClashTemp::guard(map);
+    assert_eq!(
+        guarded.get("external-controller").and_then(Value::as_str),
+        Some("0.0.0.0:1234")
+    );
+}
+
+#[test]
+fn test_get_tun_device_ip() {
+    fn get_case(fake_ip_range: &str) -> String {
+        let mut dns = Mapping::new();
+        dns.insert("fake-ip-range".into(), fake_ip_range.into());
+        let mut map = Mapping::new();
+        map.insert("dns".into(), Value::Mapping(dns));
+        IClashTemp(map).get_tun_device_ip()
+    }
+
+    // default range stays the same as before
+    assert_eq!(get_case("198.18.0.1/16"), "198.18.0.2");
+
+    // a custom, non-/16 range is now computed numerically instead of string-patched
+    assert_eq!(get_case("100.64.0.1/10"), "100.64.0.2");
+
+    // ipv6 fake ranges are supported
+    assert_eq!(get_case("fdfe:dcba:9876::1/64"), "fdfe:dcba:9876::2");
+
+    // unparsable or missing values fall back to the default device ip
+    assert_eq!(get_case("not-a-cidr"), "198.18.0.2");
+    assert_eq!(IClashTemp(Mapping::new()).get_tun_device_ip(), "198.18.0.2");
+}
+
+#[test]
+fn test_validate() {
+    assert!(IClashTemp(Mapping::new()).validate().is_empty());
+
+    // allow-lan + wildcard controller + no secret is flagged as an error
+    let mut map = Mapping::new();
+    map.insert("allow-lan".into(), true.into());
+    map.insert("external-controller".into(), "0.0.0.0:9090".into());
+    let diagnostics = IClashTemp(map).validate();
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.severity == ClashConfigSeverity::Error && d.key == "external-controller"));
+
+    // same wildcard controller is fine once a secret is set
+    let mut map = Mapping::new();
+    map.insert("allow-lan".into(), true.into());
+    map.insert("external-controller".into(), "0.0.0.0:9090".into());
+    map.insert("secret".into(), "s3cr3t".into());
+    assert!(IClashTemp(map).validate().is_empty());
+
+    // mixed-port colliding with the external controller port is an error
+    let mut map = Mapping::new();
+    map.insert("mixed-port".into(), 9090.into());
+    map.insert("external-controller".into(), "127.0.0.1:9090".into());
+    let diagnostics = IClashTemp(map).validate();
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.severity == ClashConfigSeverity::Error && d.key == "mixed-port"));
+
+    // TUN enabled without fake-ip dns is a warning
+    let mut tun = Mapping::new();
+    tun.insert("enable".into(), true.into());
+    let mut map = Mapping::new();
+    map.insert("tun".into(), Value::Mapping(tun));
+    let diagnostics = IClashTemp(map).validate();
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.severity == ClashConfigSeverity::Warning && d.key == "dns.enhanced-mode"));
+
+    // an unparsable nameserver entry is a warning
+    let mut dns = Mapping::new();
+    dns.insert(
+        "nameserver".into(),
+        Value::Sequence(vec!["not a nameserver".into()]),
+    );
+    let mut map = Mapping::new();
+    map.insert("dns".into(), Value::Mapping(dns));
+    let diagnostics = IClashTemp(map).validate();
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.severity == ClashConfigSeverity::Warning && d.key == "dns.nameserver"));
+}
+
+#[test]
+fn test_guard_tun_mtu_skips_when_not_applicable() {
+    fn tun_mtu(config: &Mapping) -> Option<u64> {
+        config
+            .get("tun")
+            .and_then(Value::as_mapping)
+            .and_then(|tun| tun.get("mtu"))
+            .and_then(Value::as_u64)
+    }
+
+    // TUN disabled: nothing to guard
+    let mut tun = Mapping::new();
+    tun.insert("enable".into(), false.into());
+    let mut config = Mapping::new();
+    config.insert("tun".into(), Value::Mapping(tun));
+    let guarded = IClashTemp::guard(config);
+    assert_eq!(tun_mtu(&guarded), None);
+
+    // mtu already set: left untouched even with TUN enabled
+    let mut tun = Mapping::new();
+    tun.insert("enable".into(), true.into());
+    tun.insert("mtu".into(), 1350.into());
+    let mut config = Mapping::new();
+    config.insert("tun".into(), Value::Mapping(tun));
+    let guarded = IClashTemp::guard(config);
+    assert_eq!(tun_mtu(&guarded), Some(1350));
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn test_execute_hook_script_runs_with_env() {
+    let file = std::env::temp_dir().join(format!("nyanpasu-hook-test-{}.txt", std::process::id()));
+    let script = format!("echo \"$CLASH_MODE:$CLASH_MIXED_PORT\" > {}", file.display());
+    let env = [
+        ("CLASH_MODE", "rule".to_string()),
+        ("CLASH_MIXED_PORT", "7890".to_string()),
+    ];
+
+    let result = IClashTemp::execute_hook_script(&script, &env, std::time::Duration::from_secs(5));
+    assert!(result.is_ok());
+
+    let contents = std::fs::read_to_string(&file).unwrap();
+    std::fs::remove_file(&file).ok();
+    assert_eq!(contents.trim(), "rule:7890");
+}
+
+#[test]
+#[cfg(not(target_os = "windows"))]
+fn test_execute_hook_script_times_out() {
+    let result =
+        IClashTemp::execute_hook_script("sleep 5", &[], std::time::Duration::from_millis(50));
+
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("timed out"));
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub struct IClash {
@@ -276,10 +983,24 @@ pub struct IClash {
     pub ipv6: Option<bool>,
     pub mode: Option<String>,
     pub external_controller: Option<String>,
+    pub external_controller_unix: Option<String>,
     pub secret: Option<String>,
     pub dns: Option<IClashDNS>,
     pub tun: Option<IClashTUN>,
     pub interface_name: Option<String>,
+    pub hooks: Option<IClashHooks>,
+}
+
+/// Lifecycle hook scripts run around core start/stop, letting users set up or tear
+/// down firewall rules, routing, or notifications without modifying Nyanpasu itself.
+#[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct IClashHooks {
+    pub pre_start: Option<String>,
+    pub post_start: Option<String>,
+    pub pre_stop: Option<String>,
+    pub post_stop: Option<String>,
+    pub on_config_change: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
@@ -290,6 +1011,7 @@ pub struct IClashTUN {
     pub auto_route: Option<bool>,
     pub auto_detect_interface: Option<bool>,
     pub dns_hijack: Option<Vec<String>>,
+    pub mtu: Option<u16>,
 }
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]